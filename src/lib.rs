@@ -1,4 +1,4 @@
-use std::collections::{BTreeMap, BTreeSet, HashMap};
+use std::collections::{BTreeMap, HashMap};
 
 use petgraph::graph::NodeIndex;
 
@@ -10,7 +10,7 @@ pub enum BasicCardinalPoint {
     West,
 }
 
-#[derive(Copy, Clone)]
+#[derive(Copy, Clone, PartialEq, Eq, Hash)]
 pub enum Direction {
     Vertical,
     Horizontal,
@@ -40,13 +40,24 @@ pub enum BendDirection {
     Unknown,
 }
 
-#[derive(Copy, Clone, PartialEq, Eq, Hash)]
+#[derive(Copy, Clone)]
+pub enum SmoothCommand {
+    LineTo(Point),
+    QuadTo { control: Point, to: Point },
+}
+
+pub struct SmoothPath {
+    pub start: Point,
+    pub commands: Vec<SmoothCommand>,
+}
+
+#[derive(Copy, Clone, PartialEq, Eq, Hash, Debug)]
 pub struct Point {
     pub x: i32,
     pub y: i32,
 }
 
-#[derive(Copy, Clone)]
+#[derive(Copy, Clone, PartialEq, Eq, Hash)]
 pub struct Size {
     pub width: i32,
     pub height: i32,
@@ -62,7 +73,7 @@ pub struct Line {
     pub b: Point,
 }
 
-#[derive(Copy, Clone)]
+#[derive(Copy, Clone, PartialEq, Eq, Hash)]
 pub struct Rect {
     pub origin: Point,
     pub size: Size,
@@ -73,6 +84,89 @@ pub struct ConnectorPoint {
     pub shape: Rect,
     pub side: Side,
     pub distance: f64,
+    pub rotation: i32,
+}
+
+#[derive(Copy, Clone, PartialEq, Eq)]
+pub struct Vector {
+    pub x: i32,
+    pub y: i32,
+}
+
+impl std::ops::Sub for Point {
+    type Output = Vector;
+    fn sub(self, rhs: Point) -> Vector {
+        Vector {
+            x: self.x - rhs.x,
+            y: self.y - rhs.y,
+        }
+    }
+}
+
+impl std::ops::Add<Vector> for Point {
+    type Output = Point;
+    fn add(self, rhs: Vector) -> Point {
+        make_point(self.x + rhs.x, self.y + rhs.y)
+    }
+}
+
+impl std::ops::Neg for Vector {
+    type Output = Vector;
+    fn neg(self) -> Vector {
+        Vector {
+            x: -self.x,
+            y: -self.y,
+        }
+    }
+}
+
+impl std::ops::Mul<i32> for Vector {
+    type Output = Vector;
+    fn mul(self, scale: i32) -> Vector {
+        Vector {
+            x: self.x * scale,
+            y: self.y * scale,
+        }
+    }
+}
+
+impl Point {
+    pub fn dot(&self, other: &Point) -> i32 {
+        self.x * other.x + self.y * other.y
+    }
+
+    pub fn signum(&self) -> Point {
+        make_point(self.x.signum(), self.y.signum())
+    }
+
+    pub fn abs(&self) -> Point {
+        make_point(self.x.abs(), self.y.abs())
+    }
+
+    pub fn transform(&self, matrix: &[i32; 4]) -> Point {
+        make_point(
+            matrix[0] * self.x + matrix[1] * self.y,
+            matrix[2] * self.x + matrix[3] * self.y,
+        )
+    }
+
+    pub fn clamp(&self, rect: &Rect) -> Point {
+        make_point(
+            self.x.clamp(rect.left(), rect.right()),
+            self.y.clamp(rect.top(), rect.bottom()),
+        )
+    }
+}
+
+// 90-degree-multiple rotation matrices, matching the integer grid the rest
+// of the geometry lives on; anything else falls back to identity.
+fn rotation_matrix(degrees: i32) -> [i32; 4] {
+    match degrees.rem_euclid(360) {
+        90 => [0, -1, 1, 0],
+        180 => [-1, 0, 0, -1],
+        270 => [0, 1, -1, 0],
+        _ => [1, 0, 0, 1],
+    }
 }
 
 pub struct OrthogonalConnectorByproduct {
@@ -89,6 +183,15 @@ pub struct OrthogonalConnectorOpts {
     pub shape_margin: i32,
     pub global_bounds_margin: i32,
     pub global_bounds: Rect,
+    pub obstacles: Vec<Rect>,
+    pub bend_penalty: f64,
+}
+
+fn side_exit_direction(side: Side) -> Direction {
+    match side {
+        Side::Top | Side::Bottom => Direction::Horizontal,
+        Side::Left | Side::Right => Direction::Vertical,
+    }
 }
 
 pub const fn make_point(x: i32, y: i32) -> Point {
@@ -103,6 +206,23 @@ fn min_max(x: &[i32]) -> (i32, i32) {
     (*x.iter().min().unwrap(), *x.iter().max().unwrap())
 }
 
+impl ConnectorPoint {
+    // The anchor on an unrotated shape, then rotated about the shape's
+    // center by `rotation` degrees so connectors can attach to rotated
+    // shapes without the rest of the router knowing about rotation at all.
+    fn anchor(&self) -> Point {
+        let local = self.shape.side_point(self.side, self.distance);
+        let center = self.shape.center();
+        let offset = local - center;
+        let rotated = make_point(offset.x, offset.y).transform(&rotation_matrix(self.rotation));
+        center
+            + Vector {
+                x: rotated.x,
+                y: rotated.y,
+            }
+    }
+}
+
 impl Rect {
     const EMPTY: Rect = Rect {
         origin: make_point(0, 0),
@@ -116,14 +236,6 @@ impl Rect {
         }
     }
 
-    fn size(&self) -> Size {
-        self.size
-    }
-
-    fn location(&self) -> Point {
-        self.origin
-    }
-
     fn left(&self) -> i32 {
         self.origin.x
     }
@@ -148,42 +260,6 @@ impl Rect {
         self.size.height
     }
 
-    fn north_east(&self) -> Point {
-        make_point(self.right(), self.top())
-    }
-
-    fn south_east(&self) -> Point {
-        make_point(self.right(), self.bottom())
-    }
-
-    fn south_west(&self) -> Point {
-        make_point(self.left(), self.bottom())
-    }
-
-    fn north_west(&self) -> Point {
-        make_point(self.left(), self.top())
-    }
-
-    fn east(&self) -> Point {
-        make_point(self.left(), self.center().y)
-    }
-
-    fn north(&self) -> Point {
-        make_point(self.center().x, self.top())
-    }
-
-    fn south(&self) -> Point {
-        make_point(self.center().x, self.bottom())
-    }
-
-    fn west(&self) -> Point {
-        make_point(self.left(), self.center().y)
-    }
-
-    fn contains(&self, p: &Point) -> bool {
-        p.x >= self.left() && p.x <= self.right() && p.y >= self.top() && p.y <= self.bottom()
-    }
-
     fn inflate(&self, horizontal: i32, vertical: i32) -> Self {
         Self::from_ltrb(
             self.left() - horizontal,
@@ -222,6 +298,187 @@ impl Rect {
             self.top() + self.height() / 2,
         )
     }
+
+    fn side_point(&self, side: Side, distance: f64) -> Point {
+        match side {
+            Side::Top => make_point(
+                self.left() + (self.width() as f64 * distance) as i32,
+                self.top(),
+            ),
+            Side::Bottom => make_point(
+                self.left() + (self.width() as f64 * distance) as i32,
+                self.bottom(),
+            ),
+            Side::Left => make_point(
+                self.left(),
+                self.top() + (self.height() as f64 * distance) as i32,
+            ),
+            Side::Right => make_point(
+                self.right(),
+                self.top() + (self.height() as f64 * distance) as i32,
+            ),
+        }
+    }
+}
+
+// A segment tree over a sorted, deduplicated set of ruler coordinates,
+// answering "all rulers in [lo, hi]" as a plain slice and an arbitrary
+// aggregate (count, min/max gap, lane density, ...) over the same range in
+// O(log n + k) instead of re-filtering the whole coordinate list.
+struct RulerIndex<T> {
+    coords: Vec<i32>,
+    tree: Vec<T>,
+    identity: T,
+    combine: Box<dyn Fn(T, T) -> T>,
+}
+
+impl<T: Copy> RulerIndex<T> {
+    fn build(
+        mut coords: Vec<i32>,
+        value_at: impl Fn(i32) -> T,
+        identity: T,
+        combine: impl Fn(T, T) -> T + 'static,
+    ) -> Self {
+        coords.sort_unstable();
+        coords.dedup();
+        let n = coords.len();
+        let mut tree = vec![identity; 2 * n.max(1)];
+        for (i, &c) in coords.iter().enumerate() {
+            tree[n + i] = value_at(c);
+        }
+        for i in (1..n).rev() {
+            tree[i] = combine(tree[2 * i], tree[2 * i + 1]);
+        }
+        RulerIndex {
+            coords,
+            tree,
+            identity,
+            combine: Box::new(combine),
+        }
+    }
+
+    fn rulers_in(&self, lo: i32, hi: i32) -> &[i32] {
+        let l = self.coords.partition_point(|&c| c < lo);
+        let r = self.coords.partition_point(|&c| c <= hi);
+        &self.coords[l..r]
+    }
+
+    fn aggregate(&self, lo: i32, hi: i32) -> T {
+        let n = self.coords.len();
+        if n == 0 {
+            return self.identity;
+        }
+        let l = self.coords.partition_point(|&c| c < lo);
+        let r = self.coords.partition_point(|&c| c <= hi);
+        let (mut l, mut r) = (l + n, r + n);
+        let mut res_l = self.identity;
+        let mut res_r = self.identity;
+        while l < r {
+            if l % 2 == 1 {
+                res_l = (self.combine)(res_l, self.tree[l]);
+                l += 1;
+            }
+            if r % 2 == 1 {
+                r -= 1;
+                res_r = (self.combine)(self.tree[r], res_r);
+            }
+            l /= 2;
+            r /= 2;
+        }
+        (self.combine)(res_l, res_r)
+    }
+}
+
+const QUADTREE_CAPACITY: usize = 8;
+const QUADTREE_MAX_DEPTH: usize = 8;
+
+struct Quadtree<T> {
+    bounds: Rect,
+    items: Vec<(Rect, T)>,
+    children: Option<Box<[Quadtree<T>; 4]>>,
+}
+
+impl<T> Quadtree<T> {
+    fn new(bounds: Rect) -> Self {
+        Quadtree {
+            bounds,
+            items: Vec::new(),
+            children: None,
+        }
+    }
+
+    fn build<I: IntoIterator<Item = (Rect, T)>>(bounds: Rect, items: I) -> Self {
+        let mut tree = Self::new(bounds);
+        for (rect, value) in items {
+            tree.insert(rect, value);
+        }
+        tree
+    }
+
+    fn insert(&mut self, rect: Rect, value: T) {
+        self.insert_at(rect, value, 0);
+    }
+
+    fn insert_at(&mut self, rect: Rect, value: T, depth: usize) {
+        if self.children.is_none() && (self.items.len() < QUADTREE_CAPACITY || depth >= QUADTREE_MAX_DEPTH) {
+            self.items.push((rect, value));
+            return;
+        }
+        if self.children.is_none() {
+            self.subdivide();
+        }
+        let quadrant = self
+            .children
+            .as_ref()
+            .unwrap()
+            .iter()
+            .position(|child| Self::fits(&child.bounds, &rect));
+        match quadrant {
+            Some(index) => self.children.as_mut().unwrap()[index].insert_at(rect, value, depth + 1),
+            None => self.items.push((rect, value)),
+        }
+    }
+
+    fn fits(container: &Rect, rect: &Rect) -> bool {
+        rect.left() >= container.left()
+            && rect.right() <= container.right()
+            && rect.top() >= container.top()
+            && rect.bottom() <= container.bottom()
+    }
+
+    fn subdivide(&mut self) {
+        let b = self.bounds;
+        let cx = b.left() + b.width() / 2;
+        let cy = b.top() + b.height() / 2;
+        self.children = Some(Box::new([
+            Quadtree::new(Rect::from_ltrb(b.left(), b.top(), cx, cy)),
+            Quadtree::new(Rect::from_ltrb(cx, b.top(), b.right(), cy)),
+            Quadtree::new(Rect::from_ltrb(b.left(), cy, cx, b.bottom())),
+            Quadtree::new(Rect::from_ltrb(cx, cy, b.right(), b.bottom())),
+        ]));
+    }
+
+    fn query(&self, area: Rect) -> impl Iterator<Item = &T> {
+        let mut matches = Vec::new();
+        self.query_into(&area, &mut matches);
+        matches.into_iter()
+    }
+
+    fn query_into<'a>(&'a self, area: &Rect, out: &mut Vec<&'a T>) {
+        if !self.bounds.intersects(area) {
+            return;
+        }
+        for (rect, value) in &self.items {
+            if rect.intersects(area) {
+                out.push(value);
+            }
+        }
+        if let Some(children) = &self.children {
+            for child in children.iter() {
+                child.query_into(area, out);
+            }
+        }
+    }
 }
 
 struct PointGraph {
@@ -239,19 +496,561 @@ impl PointGraph {
             ndx
         }
     }
-    fn connect(&mut self, a: Point, b: Point) {
+    fn get(&self, p: &Point) -> Option<NodeIndex> {
+        self.nodes.get(p).cloned()
+    }
+    fn direction_of(&self, a: NodeIndex, b: NodeIndex) -> Direction {
+        direction_of(&self.graph[a], &self.graph[b])
+    }
+
+    fn connect(&mut self, a: Point, b: Point, obstacles: &Quadtree<Rect>) -> bool {
+        self.connect_except(a, b, obstacles, Rect::EMPTY)
+    }
+
+    // Like `connect`, but ignores crossings against `except` — needed when
+    // linking a connector's own anchor out to the grid, since the anchor
+    // necessarily sits inside its own shape's inflated margin.
+    fn connect_except(&mut self, a: Point, b: Point, obstacles: &Quadtree<Rect>, except: Rect) -> bool {
+        if segment_crosses_any(&a, &b, obstacles, except) {
+            return false;
+        }
         let weight = distance(a, b);
         let a = self.get(&a).unwrap();
         let b = self.get(&b).unwrap();
         self.graph.add_edge(a, b, weight);
+        true
     }
-    fn has(&self, p: &Point) -> bool {
-        self.nodes.contains_key(p)
+}
+
+// Connector segments are always axis-aligned, so crossing a rect means the
+// segment's perpendicular coordinate lies strictly inside the rect while its
+// span overlaps the rect's extent along its own axis.
+fn segment_crosses_rect(a: &Point, b: &Point, rect: &Rect) -> bool {
+    let (lo_x, hi_x) = (a.x.min(b.x), a.x.max(b.x));
+    let (lo_y, hi_y) = (a.y.min(b.y), a.y.max(b.y));
+    if a.y == b.y {
+        a.y > rect.top() && a.y < rect.bottom() && lo_x < rect.right() && hi_x > rect.left()
+    } else {
+        a.x > rect.left() && a.x < rect.right() && lo_y < rect.bottom() && hi_y > rect.top()
     }
-    fn get(&self, p: &Point) -> Option<NodeIndex> {
-        self.nodes.get(p).cloned()
+}
+
+fn segment_crosses_any(a: &Point, b: &Point, obstacles: &Quadtree<Rect>, except: Rect) -> bool {
+    let bounds = Rect::from_ltrb(a.x.min(b.x), a.y.min(b.y), a.x.max(b.x), a.y.max(b.y));
+    obstacles
+        .query(bounds)
+        .any(|r| *r != except && segment_crosses_rect(a, b, r))
+}
+
+fn unique_sorted(mut xs: Vec<i32>) -> Vec<i32> {
+    xs.sort_unstable();
+    xs.dedup();
+    xs
+}
+
+fn build_rulers(opts: &OrthogonalConnectorOpts) -> (Vec<i32>, Vec<i32>) {
+    let mut h = vec![opts.global_bounds.top(), opts.global_bounds.bottom()];
+    let mut v = vec![opts.global_bounds.left(), opts.global_bounds.right()];
+
+    let mut shapes = vec![opts.point_a.shape, opts.point_b.shape];
+    shapes.extend(opts.obstacles.iter().copied());
+
+    for shape in &shapes {
+        let inflated = shape.inflate(opts.shape_margin, opts.shape_margin);
+        h.push(inflated.top());
+        h.push(inflated.bottom());
+        v.push(inflated.left());
+        v.push(inflated.right());
     }
-    fn direction_of(&self, a: NodeIndex, b: NodeIndex) -> Direction {
-        direction_of(&self.graph[a], &self.graph[b])
+
+    // The anchors themselves must land exactly on a ruler so the spot grid
+    // has a row/column through them to connect to.
+    for anchor in [opts.point_a.anchor(), opts.point_b.anchor()] {
+        h.push(anchor.y);
+        v.push(anchor.x);
+    }
+
+    (unique_sorted(h), unique_sorted(v))
+}
+
+// Wraps a ruler coordinate list in a RulerIndex so build_grid can slice the
+// rulers falling inside a region in O(log n + k) instead of filtering the
+// whole vector.
+fn ruler_index(rulers: &[i32]) -> RulerIndex<usize> {
+    RulerIndex::build(rulers.to_vec(), |_| 1usize, 0usize, |a, b| a + b)
+}
+
+// Counts how many routing lanes (h_rulers or v_rulers) fall in `[lo, hi]`,
+// via the segment-tree aggregate of the same RulerIndex the grid builder
+// slices its rulers with, so callers can gauge routing-lane density in a
+// region. The index is built over `rulers` afresh on each call.
+pub fn lane_density(rulers: &[i32], lo: i32, hi: i32) -> usize {
+    ruler_index(rulers).aggregate(lo, hi)
+}
+
+// Builds the flat grid cells (kept identical for the public byproduct) from
+// the rulers spanning `bounds`.
+fn build_grid(h_rulers: &RulerIndex<usize>, v_rulers: &RulerIndex<usize>, bounds: Rect) -> Vec<Rect> {
+    let mut grid = Vec::new();
+    let h_slice = h_rulers.rulers_in(bounds.top(), bounds.bottom());
+    let v_slice = v_rulers.rulers_in(bounds.left(), bounds.right());
+    for top_bottom in h_slice.windows(2) {
+        for left_right in v_slice.windows(2) {
+            grid.push(Rect::from_ltrb(
+                left_right[0],
+                top_bottom[0],
+                left_right[1],
+                top_bottom[1],
+            ));
+        }
+    }
+    grid
+}
+
+// Walkable spots are ruler intersections rather than grid-cell centers: the
+// router (and anchors, which are seeded as rulers themselves) travels along
+// ruler lines and bends at their intersections, so a spot must sit exactly
+// on a shared row/column for `build_graph`/`connect_anchor` to link it up.
+fn build_spots(h_rulers: &[i32], v_rulers: &[i32], obstacles: &Quadtree<Rect>) -> Vec<Point> {
+    let mut spots = Vec::new();
+    for &y in h_rulers {
+        for &x in v_rulers {
+            let probe = Rect::from_ltrb(x, y, x, y);
+            if obstacles.query(probe).next().is_none() {
+                spots.push(make_point(x, y));
+            }
+        }
+    }
+    spots
+}
+
+fn build_graph(spots: &[Point], obstacles: &Quadtree<Rect>) -> PointGraph {
+    let mut graph = PointGraph {
+        graph: petgraph::Graph::new(),
+        nodes: HashMap::new(),
+    };
+    for &spot in spots {
+        graph.add(spot);
+    }
+    // Grid spots line up on shared rulers; connect a spot to its nearest
+    // neighbor to the right on the same row and below on the same column,
+    // skipping the edge when an inflated obstacle lies across it.
+    let mut by_row: BTreeMap<i32, Vec<i32>> = BTreeMap::new();
+    let mut by_col: BTreeMap<i32, Vec<i32>> = BTreeMap::new();
+    for spot in spots {
+        by_row.entry(spot.y).or_default().push(spot.x);
+        by_col.entry(spot.x).or_default().push(spot.y);
+    }
+    for (y, xs) in by_row.iter_mut() {
+        xs.sort_unstable();
+        xs.dedup();
+        for pair in xs.windows(2) {
+            graph.connect(make_point(pair[0], *y), make_point(pair[1], *y), obstacles);
+        }
+    }
+    for (x, ys) in by_col.iter_mut() {
+        ys.sort_unstable();
+        ys.dedup();
+        for pair in ys.windows(2) {
+            graph.connect(make_point(*x, pair[0]), make_point(*x, pair[1]), obstacles);
+        }
+    }
+    graph
+}
+
+fn connect_anchor(
+    graph: &mut PointGraph,
+    anchor: Point,
+    own_shape: Rect,
+    spots: &[Point],
+    obstacles: &Quadtree<Rect>,
+) {
+    graph.add(anchor);
+    for &spot in spots {
+        if spot.x == anchor.x || spot.y == anchor.y {
+            graph.connect_except(anchor, spot, obstacles, own_shape);
+        }
+    }
+}
+
+#[derive(Copy, Clone, PartialEq)]
+struct BendState {
+    cost: f64,
+    node: NodeIndex,
+    dir: Direction,
+}
+
+impl Eq for BendState {}
+
+impl Ord for BendState {
+    fn cmp(&self, other: &Self) -> std::cmp::Ordering {
+        other.cost.partial_cmp(&self.cost).unwrap()
+    }
+}
+
+impl PartialOrd for BendState {
+    fn partial_cmp(&self, other: &Self) -> Option<std::cmp::Ordering> {
+        Some(self.cmp(other))
+    }
+}
+
+fn edge_weight(graph: &PointGraph, a: NodeIndex, b: NodeIndex) -> f64 {
+    let (edge, _) = graph.graph.find_edge_undirected(a, b).unwrap();
+    graph.graph[edge]
+}
+
+// Dijkstra over the state space (node, arrival direction), so a transition
+// that changes direction pays `bend_penalty` on top of its geometric
+// distance. The start is seeded with `start_dir` (point_a's required exit
+// direction, no penalty if the first edge already agrees with it); arriving
+// at `goal` pays one more potential penalty if the arrival direction doesn't
+// match point_b's required exit direction.
+fn bend_minimizing_path(
+    graph: &PointGraph,
+    start: NodeIndex,
+    start_dir: Direction,
+    goal: NodeIndex,
+    goal_dir: Direction,
+    bend_penalty: f64,
+) -> Vec<Point> {
+    use std::collections::BinaryHeap;
+
+    let mut dist: HashMap<(NodeIndex, Direction), f64> = HashMap::new();
+    let mut prev: HashMap<(NodeIndex, Direction), (NodeIndex, Direction)> = HashMap::new();
+    let mut heap = BinaryHeap::new();
+
+    dist.insert((start, start_dir), 0.0);
+    heap.push(BendState {
+        cost: 0.0,
+        node: start,
+        dir: start_dir,
+    });
+
+    // Edges are axis-aligned, so a node is only ever reached going
+    // Horizontal or Vertical (Other never occurs) — once both of the goal's
+    // two possible arrival states have been popped off the heap, Dijkstra has
+    // already finalized their costs and best_goal can't improve further.
+    let mut best_goal: Option<(f64, Direction)> = None;
+    let mut seen_horizontal = false;
+    let mut seen_vertical = false;
+
+    while let Some(BendState { cost, node, dir }) = heap.pop() {
+        if dist.get(&(node, dir)).is_some_and(|&best| cost > best) {
+            continue;
+        }
+        if node == goal {
+            let finish = cost + if dir != goal_dir { bend_penalty } else { 0.0 };
+            if best_goal.is_none_or(|(best, _)| finish < best) {
+                best_goal = Some((finish, dir));
+            }
+            match dir {
+                Direction::Horizontal => seen_horizontal = true,
+                Direction::Vertical => seen_vertical = true,
+                Direction::Other => {}
+            }
+            if seen_horizontal && seen_vertical {
+                break;
+            }
+        }
+        for neighbor in graph.graph.neighbors_undirected(node) {
+            let step = edge_weight(graph, node, neighbor);
+            let edge_dir = graph.direction_of(node, neighbor);
+            let next_cost = cost + step + if edge_dir != dir { bend_penalty } else { 0.0 };
+            let key = (neighbor, edge_dir);
+            if dist.get(&key).is_none_or(|&best| next_cost < best) {
+                dist.insert(key, next_cost);
+                prev.insert(key, (node, dir));
+                heap.push(BendState {
+                    cost: next_cost,
+                    node: neighbor,
+                    dir: edge_dir,
+                });
+            }
+        }
+    }
+
+    let mut path = Vec::new();
+    if let Some((_, dir)) = best_goal {
+        let mut current = (goal, dir);
+        path.push(graph.graph[current.0]);
+        while current.0 != start || current.1 != start_dir {
+            match prev.get(&current) {
+                Some(&p) => {
+                    path.push(graph.graph[p.0]);
+                    current = p;
+                }
+                None => break,
+            }
+        }
+        path.reverse();
+    }
+    path
+}
+
+pub fn orthogonal_connector(opts: &OrthogonalConnectorOpts) -> OrthogonalConnectorByproduct {
+    let (h_rulers, v_rulers) = build_rulers(opts);
+
+    let mut shapes = vec![opts.point_a.shape, opts.point_b.shape];
+    shapes.extend(opts.obstacles.iter().copied());
+    let inflated: Vec<Rect> = shapes
+        .iter()
+        .map(|s| s.inflate(opts.shape_margin, opts.shape_margin))
+        .collect();
+
+    let index_bounds = inflated
+        .iter()
+        .fold(opts.global_bounds, |acc, r| acc.union(r));
+    let obstacle_index = Quadtree::build(index_bounds, inflated.iter().map(|r| (*r, *r)));
+
+    let grid = build_grid(&ruler_index(&h_rulers), &ruler_index(&v_rulers), index_bounds);
+    let spots = build_spots(&h_rulers, &v_rulers, &obstacle_index);
+
+    let point_a = opts.point_a.anchor();
+    let point_b = opts.point_b.anchor();
+
+    let mut graph = build_graph(&spots, &obstacle_index);
+    connect_anchor(&mut graph, point_a, inflated[0], &spots, &obstacle_index);
+    connect_anchor(&mut graph, point_b, inflated[1], &spots, &obstacle_index);
+
+    let start = graph.get(&point_a).unwrap();
+    let goal = graph.get(&point_b).unwrap();
+    let path = bend_minimizing_path(
+        &graph,
+        start,
+        side_exit_direction(opts.point_a.side),
+        goal,
+        side_exit_direction(opts.point_b.side),
+        opts.bend_penalty,
+    );
+
+    let connections = path
+        .windows(2)
+        .map(|pair| Line {
+            a: pair[0],
+            b: pair[1],
+        })
+        .collect();
+
+    OrthogonalConnectorByproduct {
+        h_rulers,
+        v_rulers,
+        spots,
+        grid,
+        connections,
+    }
+}
+
+fn classify_bend(prev: Point, corner: Point, next: Point) -> BendDirection {
+    match (direction_of(&prev, &corner), direction_of(&corner, &next)) {
+        (Direction::Horizontal, Direction::Vertical) | (Direction::Vertical, Direction::Horizontal) => {
+            let hsign = if prev.x != corner.x {
+                (corner.x - prev.x).signum()
+            } else {
+                (next.x - corner.x).signum()
+            };
+            let vsign = if prev.y != corner.y {
+                (corner.y - prev.y).signum()
+            } else {
+                (next.y - corner.y).signum()
+            };
+            let cardinal = match (hsign >= 0, vsign >= 0) {
+                (true, false) => BasicCardinalPoint::East,
+                (false, false) => BasicCardinalPoint::West,
+                (true, true) => BasicCardinalPoint::South,
+                (false, true) => BasicCardinalPoint::North,
+            };
+            BendDirection::Cardinal(cardinal)
+        }
+        _ => BendDirection::Unknown,
+    }
+}
+
+fn point_towards(from: Point, to: Point, dist: i32) -> Point {
+    if from.y == to.y {
+        let dir = (to.x - from.x).signum();
+        make_point(from.x + dir * dist, from.y)
+    } else {
+        let dir = (to.y - from.y).signum();
+        make_point(from.x, from.y + dir * dist)
+    }
+}
+
+// Replaces each interior right-angle vertex of a connector polyline with a
+// quadratic bezier whose control point is the original corner, so callers
+// can emit smooth SVG `path` data. The back-off distance on each incident
+// segment is capped at half that segment's length, which both respects `r`
+// and guarantees two adjacent corners on a short segment never overlap.
+pub fn smooth_path(connections: &[Line], radius: i32) -> SmoothPath {
+    if connections.is_empty() {
+        return SmoothPath {
+            start: make_point(0, 0),
+            commands: Vec::new(),
+        };
+    }
+
+    let mut points = vec![connections[0].a];
+    points.extend(connections.iter().map(|line| line.b));
+
+    let start = points[0];
+    let mut commands = Vec::new();
+
+    for i in 1..points.len() - 1 {
+        let prev = points[i - 1];
+        let corner = points[i];
+        let next = points[i + 1];
+
+        if !matches!(classify_bend(prev, corner, next), BendDirection::Cardinal(_)) {
+            commands.push(SmoothCommand::LineTo(corner));
+            continue;
+        }
+
+        let r_in = radius.min((distance(prev, corner) / 2.0) as i32);
+        let r_out = radius.min((distance(corner, next) / 2.0) as i32);
+
+        commands.push(SmoothCommand::LineTo(point_towards(corner, prev, r_in)));
+        commands.push(SmoothCommand::QuadTo {
+            control: corner,
+            to: point_towards(corner, next, r_out),
+        });
+    }
+
+    commands.push(SmoothCommand::LineTo(*points.last().unwrap()));
+
+    SmoothPath { start, commands }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn segment_crosses_rect_only_when_path_enters_it() {
+        let rect = Rect::from_ltrb(10, 10, 20, 20);
+        assert!(segment_crosses_rect(&make_point(0, 15), &make_point(30, 15), &rect));
+        assert!(!segment_crosses_rect(&make_point(0, 5), &make_point(30, 5), &rect));
+    }
+
+    #[test]
+    fn connect_refuses_an_edge_crossing_an_inflated_obstacle() {
+        let obstacle = Rect::from_ltrb(10, 0, 20, 20);
+        let index = Quadtree::build(Rect::from_ltrb(-50, -50, 50, 50), [(obstacle, obstacle)]);
+        let mut graph = PointGraph {
+            graph: petgraph::Graph::new(),
+            nodes: HashMap::new(),
+        };
+        let a = make_point(0, 10);
+        let b = make_point(30, 10);
+        graph.add(a);
+        graph.add(b);
+        assert!(!graph.connect(a, b, &index));
+
+        let c = make_point(0, 30);
+        let d = make_point(30, 30);
+        graph.add(c);
+        graph.add(d);
+        assert!(graph.connect(c, d, &index));
+    }
+
+    #[test]
+    fn bend_penalty_prefers_fewer_bends_over_shorter_distance() {
+        let mut graph = PointGraph {
+            graph: petgraph::Graph::new(),
+            nodes: HashMap::new(),
+        };
+        let no_obstacles = Quadtree::<Rect>::new(Rect::from_ltrb(-1000, -1000, 1000, 1000));
+
+        let a = make_point(0, 0);
+        let b = make_point(100, 0);
+        // Zigzag route: shorter total distance, more bends.
+        let p = make_point(50, 0);
+        let q = make_point(50, -1);
+        let r = make_point(100, -1);
+        // Dog-leg route: longer total distance, fewer bends.
+        let s = make_point(0, -50);
+        let t = make_point(100, -50);
+
+        for pt in [a, b, p, q, r, s, t] {
+            graph.add(pt);
+        }
+        for (from, to) in [(a, p), (p, q), (q, r), (r, b), (a, s), (s, t), (t, b)] {
+            assert!(graph.connect(from, to, &no_obstacles));
+        }
+
+        let start = graph.get(&a).unwrap();
+        let goal = graph.get(&b).unwrap();
+        let bends = |path: &[Point]| {
+            path.windows(3)
+                .filter(|w| direction_of(&w[0], &w[1]) != direction_of(&w[1], &w[2]))
+                .count()
+        };
+        let length =
+            |path: &[Point]| path.windows(2).map(|w| distance(w[0], w[1])).sum::<f64>();
+
+        let distance_only = bend_minimizing_path(
+            &graph,
+            start,
+            Direction::Horizontal,
+            goal,
+            Direction::Horizontal,
+            0.0,
+        );
+        let bend_minimized = bend_minimizing_path(
+            &graph,
+            start,
+            Direction::Horizontal,
+            goal,
+            Direction::Horizontal,
+            1000.0,
+        );
+
+        assert!(length(&distance_only) < length(&bend_minimized));
+        assert!(bends(&bend_minimized) < bends(&distance_only));
+    }
+
+    #[test]
+    fn anchor_rotates_about_the_shape_center() {
+        let shape = Rect::from_ltrb(0, 0, 20, 10);
+        let cp = |rotation| ConnectorPoint {
+            shape,
+            side: Side::Right,
+            distance: 0.5,
+            rotation,
+        };
+
+        assert_eq!(cp(0).anchor(), make_point(20, 5));
+        assert_eq!(cp(90).anchor(), make_point(10, 15));
+        assert_eq!(cp(180).anchor(), make_point(0, 5));
+        assert_eq!(cp(270).anchor(), make_point(10, -5));
+    }
+
+    #[test]
+    fn smooth_path_clamps_radius_so_adjacent_corners_meet_not_overlap() {
+        // A(0,0) -> B(0,10) -> C(10,10) -> D(10,0): the B-C segment is only
+        // 10 long, so a requested radius of 100 must clamp to half of each
+        // incident segment (5) rather than have the two corners cross.
+        let a = make_point(0, 0);
+        let b = make_point(0, 10);
+        let c = make_point(10, 10);
+        let d = make_point(10, 0);
+        let connections = [Line { a, b }, Line { a: b, b: c }, Line { a: c, b: d }];
+
+        let path = smooth_path(&connections, 100);
+
+        let points: Vec<Point> = path
+            .commands
+            .iter()
+            .map(|cmd| match *cmd {
+                SmoothCommand::LineTo(p) => p,
+                SmoothCommand::QuadTo { to, .. } => to,
+            })
+            .collect();
+
+        // Back off from B towards A, then swing through B to the B-C
+        // midpoint, exactly where the back-off from C towards B picks up.
+        assert_eq!(points[0], make_point(0, 5));
+        assert_eq!(points[1], make_point(5, 10));
+        assert_eq!(points[2], make_point(5, 10));
+        assert_eq!(points[3], make_point(10, 5));
+        assert_eq!(points[4], d);
     }
 }